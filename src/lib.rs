@@ -11,13 +11,13 @@ use serde::{Deserialize, Serialize};
 use swc_core::common::{
     comments::{Comment, CommentKind, Comments, SingleThreadedComments},
     sync::Lrc,
-    FileName, SourceMap, SyntaxContext, Span,
+    BytePos, FileName, SourceMap, SyntaxContext, Span,
 };
 use swc_core::ecma::{
     ast::*,
     codegen::{text_writer::JsWriter, Config, Emitter},
-    parser::{lexer::Lexer, Parser, StringInput, Syntax},
-    visit::{VisitMut, VisitMutWith},
+    parser::{lexer::Lexer, EsConfig, Parser, StringInput, Syntax, TsConfig},
+    visit::{Visit, VisitMut, VisitMutWith, VisitWith},
 };
 
 lazy_static! {
@@ -58,39 +58,183 @@ struct BabelNode {
     // 其他字段根据需要添加
 }
 
+#[napi(object)]
+#[derive(Default)]
+pub struct TransformOptions {
+    // 按 TypeScript 语法解析（对应 deno ast.rs 的 Syntax::Typescript）
+    pub typescript: Option<bool>,
+    // 允许 TSX 语法，隐含 typescript
+    pub tsx: Option<bool>,
+    // 在 ES 模式下允许 JSX 语法
+    pub jsx: Option<bool>,
+    // 允许装饰器语法
+    pub decorators: Option<bool>,
+    // 若提供文件名，则按扩展名推断上述默认值（.ts/.tsx/.jsx）
+    pub filename: Option<String>,
+    // 将 source map 作为 base64 的 //# sourceMappingURL 注释内联到代码末尾
+    pub inline_source_map: Option<bool>,
+    // 可选的 PURE 注解规则集
+    pub pure: Option<PureConfig>,
+}
+
+// 可调的 PURE 注解规则集，供各 transform 入口透传
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct PureConfig {
+    // 额外排除的标识符（与内置 TSLIB_HELPERS 合并）
+    pub excluded_helpers: Option<Vec<String>>,
+    // 已知无副作用的被调函数，即便带参数也会被注解
+    pub pure_callees: Option<Vec<String>>,
+    // 注入的注释文本：`#`（默认）或 `@`
+    pub comment_style: Option<String>,
+    // 是否给 NewExpr 添加注解，默认开启
+    pub annotate_new_expr: Option<bool>,
+    // 仅为 VarDeclarator 的初始化器添加注解
+    pub declarator_only: Option<bool>,
+}
+
+// 经过解析的规则集，所有 Option 在此落定为具体值
+struct ResolvedPureConfig {
+    excluded: HashSet<String>,
+    pure_callees: HashSet<String>,
+    comment_text: String,
+    annotate_new_expr: bool,
+    declarator_only: bool,
+}
+
+impl Default for ResolvedPureConfig {
+    fn default() -> Self {
+        Self {
+            excluded: HashSet::new(),
+            pure_callees: HashSet::new(),
+            comment_text: "#__PURE__".to_string(),
+            annotate_new_expr: true,
+            declarator_only: false,
+        }
+    }
+}
+
+impl From<PureConfig> for ResolvedPureConfig {
+    fn from(config: PureConfig) -> Self {
+        let excluded = config
+            .excluded_helpers
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        let pure_callees = config.pure_callees.unwrap_or_default().into_iter().collect();
+        // 注释文本仅在 `@` 与默认 `#` 之间切换
+        let comment_text = match config.comment_style.as_deref() {
+            Some("@") => "@__PURE__".to_string(),
+            _ => "#__PURE__".to_string(),
+        };
+        Self {
+            excluded,
+            pure_callees,
+            comment_text,
+            annotate_new_expr: config.annotate_new_expr.unwrap_or(true),
+            declarator_only: config.declarator_only.unwrap_or(false),
+        }
+    }
+}
+
+impl TransformOptions {
+    // 依据文件扩展名推断语法开关，模仿 deno 的 MediaType 判定
+    fn apply_filename_defaults(&mut self) {
+        let Some(filename) = self.filename.as_deref() else {
+            return;
+        };
+
+        if filename.ends_with(".tsx") {
+            self.typescript.get_or_insert(true);
+            self.tsx.get_or_insert(true);
+        } else if filename.ends_with(".ts") || filename.ends_with(".mts") || filename.ends_with(".cts") {
+            self.typescript.get_or_insert(true);
+        } else if filename.ends_with(".jsx") {
+            self.jsx.get_or_insert(true);
+        }
+    }
+
+    // 将选项映射为 SWC 语法配置
+    fn to_syntax(&self) -> Syntax {
+        let decorators = self.decorators.unwrap_or(false);
+        let tsx = self.tsx.unwrap_or(false);
+
+        if self.typescript.unwrap_or(false) || tsx {
+            Syntax::Typescript(TsConfig {
+                tsx,
+                decorators,
+                ..Default::default()
+            })
+        } else {
+            Syntax::Es(EsConfig {
+                jsx: self.jsx.unwrap_or(false),
+                decorators,
+                ..Default::default()
+            })
+        }
+    }
+}
+
 #[derive(Default)]
 struct PureFunctionVisitor {
     in_top_level: bool,
+    in_declarator_init: bool,
+    // 当前调用的返回值是否被实际使用（初始化器、赋值右值、调用实参等）
+    in_value_context: bool,
     source_map: Lrc<SourceMap>,
     comments: Lrc<SingleThreadedComments>,
+    config: ResolvedPureConfig,
 }
 
 impl PureFunctionVisitor {
-    fn new(source_map: Lrc<SourceMap>, comments: Lrc<SingleThreadedComments>) -> Self {
+    fn new(
+        source_map: Lrc<SourceMap>,
+        comments: Lrc<SingleThreadedComments>,
+        config: ResolvedPureConfig,
+    ) -> Self {
         Self {
             in_top_level: true,
+            in_declarator_init: false,
+            in_value_context: false,
             source_map,
             comments,
+            config,
         }
     }
 
+    // 判断标识符是否被排除：内置 tslib 帮助函数或用户提供的额外集合
+    fn is_excluded_ident(&self, name: &str) -> bool {
+        is_tslib_helper_name(name) || self.config.excluded.contains(name)
+    }
+
     fn is_pure_candidate(&self, call: &CallExpr) -> bool {
         // 如果不是顶层表达式，不添加 PURE 注解
         if !self.in_top_level {
             return false;
         }
 
+        // 返回值被丢弃的位置（如裸表达式语句）不添加注解，PURE 注解在此无意义
+        if !self.in_value_context {
+            return false;
+        }
+
+        // 若配置要求仅注解声明初始化器，则其余位置一律跳过
+        if self.config.declarator_only && !self.in_declarator_init {
+            return false;
+        }
+
         match &call.callee {
             Callee::Expr(expr) => {
                 match &**expr {
+                    // 允许列表中的被调函数即便带参数也视为纯函数
+                    Expr::Ident(ident) if self.config.pure_callees.contains(&*ident.sym) => true,
+
                     // 排除有参数的函数表达式
-                    Expr::Arrow(arrow_expr) if !call.args.is_empty() => false,
-                    
-                    // 检查标识符是否为 TypeScript 帮助函数
-                    Expr::Ident(ident) => {
-                        !is_tslib_helper_name(&ident.sym.to_string())
-                    }
-                    
+                    Expr::Arrow(_) if !call.args.is_empty() => false,
+
+                    // 检查标识符是否被排除
+                    Expr::Ident(ident) => !self.is_excluded_ident(&ident.sym),
+
                     // 其他情况默认为纯函数
                     _ => true,
                 }
@@ -100,8 +244,12 @@ impl PureFunctionVisitor {
     }
 
     fn is_pure_new_expression(&self, _new_expr: &NewExpr) -> bool {
-        // 检查 new 表达式是否为顶层且可以添加 PURE 注解
+        // 与 is_pure_candidate 保持一致：顶层、处于值上下文、未被 declarator_only 排除，
+        // 且配置允许注解 NewExpr
         self.in_top_level
+            && self.in_value_context
+            && self.config.annotate_new_expr
+            && (!self.config.declarator_only || self.in_declarator_init)
     }
 
     fn has_pure_comment(&self, span: Span) -> bool {
@@ -114,19 +262,20 @@ impl PureFunctionVisitor {
     }
 
     fn add_pure_comment(&self, call: &mut CallExpr) {
-        let new_span = Span::new(
-            call.span.lo,
-            call.span.lo,
-            SyntaxContext::empty(),
-        );
-        
+        self.add_pure_comment_at(call.span.lo);
+    }
+
+    // 在给定位置注入 PURE 前导注释，供 CallExpr 与 NewExpr 共用
+    fn add_pure_comment_at(&self, lo: BytePos) {
+        let new_span = Span::new(lo, lo, SyntaxContext::empty());
+
         Comments::add_leading(
             &self.comments,
             new_span.lo,
             Comment {
                 kind: CommentKind::Block,
                 span: new_span,
-                text: "#__PURE__".into(),
+                text: self.config.comment_text.clone().into(),
             },
         );
     }
@@ -141,7 +290,46 @@ impl VisitMut for PureAnnotator {
         if self.visitor.is_pure_candidate(call) && !self.visitor.has_pure_comment(call.span) {
             self.visitor.add_pure_comment(call);
         }
+        // 被调函数与实参本身都消费一个值，进入值上下文
+        let old = self.visitor.in_value_context;
+        self.visitor.in_value_context = true;
         call.visit_mut_children_with(self);
+        self.visitor.in_value_context = old;
+    }
+
+    fn visit_mut_new_expr(&mut self, n: &mut NewExpr) {
+        if self.visitor.is_pure_new_expression(n) && !self.visitor.has_pure_comment(n.span) {
+            self.visitor.add_pure_comment_at(n.span.lo);
+        }
+        // new 表达式的实参同样消费值
+        let old = self.visitor.in_value_context;
+        self.visitor.in_value_context = true;
+        n.visit_mut_children_with(self);
+        self.visitor.in_value_context = old;
+    }
+
+    fn visit_mut_expr_stmt(&mut self, n: &mut ExprStmt) {
+        // 裸表达式语句的值被丢弃
+        let old = self.visitor.in_value_context;
+        self.visitor.in_value_context = false;
+        n.visit_mut_children_with(self);
+        self.visitor.in_value_context = old;
+    }
+
+    fn visit_mut_assign_expr(&mut self, n: &mut AssignExpr) {
+        // 赋值右值会被保留使用
+        let old = self.visitor.in_value_context;
+        self.visitor.in_value_context = true;
+        n.visit_mut_children_with(self);
+        self.visitor.in_value_context = old;
+    }
+
+    fn visit_mut_export_default_expr(&mut self, n: &mut ExportDefaultExpr) {
+        // `export default <expr>` 的值作为模块默认导出被使用，是典型的值上下文
+        let old = self.visitor.in_value_context;
+        self.visitor.in_value_context = true;
+        n.visit_mut_children_with(self);
+        self.visitor.in_value_context = old;
     }
 
     fn visit_mut_function(&mut self, n: &mut swc_core::ecma::ast::Function) {
@@ -157,19 +345,30 @@ impl VisitMut for PureAnnotator {
         n.visit_mut_children_with(self);
         self.visitor.in_top_level = old_top_level;
     }
+
+    fn visit_mut_var_declarator(&mut self, n: &mut VarDeclarator) {
+        // 声明初始化器是典型的值上下文，同时标记供 declarator_only 规则使用
+        let old_init = self.visitor.in_declarator_init;
+        let old_value = self.visitor.in_value_context;
+        self.visitor.in_declarator_init = true;
+        self.visitor.in_value_context = true;
+        n.visit_mut_children_with(self);
+        self.visitor.in_declarator_init = old_init;
+        self.visitor.in_value_context = old_value;
+    }
 }
 
-fn parse_js(source: &str) -> Result<(Module, Lrc<SourceMap>, Lrc<SingleThreadedComments>)> {
+fn parse_js(source: &str, syntax: Syntax, filename: FileName) -> Result<(Module, Lrc<SourceMap>, Lrc<SingleThreadedComments>)> {
     let source_map = Lrc::new(SourceMap::default());
     let comments = Lrc::new(SingleThreadedComments::default());
-    
+
     let source_file = source_map.new_source_file(
-        FileName::Anon,
+        filename,
         source.into(),
     );
 
     let lexer = Lexer::new(
-        Syntax::Es(Default::default()),
+        syntax,
         Default::default(),
         StringInput::from(&*source_file),
         Some(&comments),
@@ -184,39 +383,437 @@ fn parse_js(source: &str) -> Result<(Module, Lrc<SourceMap>, Lrc<SingleThreadedC
 }
 
 fn generate_js(module: &Module, source_map: Lrc<SourceMap>, comments: Lrc<SingleThreadedComments>) -> Result<String> {
+    let (code, _) = generate_js_with_map(module, source_map, comments, false)?;
+    Ok(code)
+}
+
+// 生成代码，并在需要时收集 source map。返回 (代码, 序列化后的 JSON map)。
+fn generate_js_with_map(
+    module: &Module,
+    source_map: Lrc<SourceMap>,
+    comments: Lrc<SingleThreadedComments>,
+    emit_source_map: bool,
+) -> Result<(String, Option<String>)> {
     let mut buf = vec![];
-    let writer = JsWriter::new(source_map.clone(), "\n", &mut buf, None);
-    let config = Config::default();
-    let mut emitter = Emitter {
-        cfg: config,
-        comments: Some(&comments),
-        cm: source_map,
-        wr: writer,
-    };
+    // 仅在需要时收集映射，避免给默认路径带来开销
+    let mut mappings = vec![];
+    let sink = emit_source_map.then_some(&mut mappings);
+
+    {
+        let writer = JsWriter::new(source_map.clone(), "\n", &mut buf, sink);
+        let config = Config::default();
+        let mut emitter = Emitter {
+            cfg: config,
+            comments: Some(&comments),
+            cm: source_map.clone(),
+            wr: writer,
+        };
+
+        emitter.emit_module(&module).map_err(|e| {
+            Error::from_reason(format!("Failed to generate JavaScript: {:?}", e))
+        })?;
+    }
 
-    emitter.emit_module(&module).map_err(|e| {
-        Error::from_reason(format!("Failed to generate JavaScript: {:?}", e))
+    let code = String::from_utf8(buf).map_err(|e| {
+        Error::from_reason(format!("Failed to convert generated code to string: {}", e))
     })?;
 
-    String::from_utf8(buf).map_err(|e| {
-        Error::from_reason(format!("Failed to convert generated code to string: {}", e))
-    })
+    let map = if emit_source_map {
+        let mut map_buf = vec![];
+        source_map
+            .build_source_map(&mappings)
+            .to_writer(&mut map_buf)
+            .map_err(|e| Error::from_reason(format!("Failed to build source map: {}", e)))?;
+        Some(String::from_utf8(map_buf).map_err(|e| {
+            Error::from_reason(format!("Failed to convert source map to string: {}", e))
+        })?)
+    } else {
+        None
+    };
+
+    Ok((code, map))
 }
 
 #[napi]
-pub fn transform(source: String) -> Result<String> {
-    // 解析 JavaScript 代码
-    let (mut module, source_map, comments) = parse_js(&source)?;
-    
-    // 创建并运行访问器
-    let visitor = PureFunctionVisitor::new(source_map.clone(), comments.clone());
-    let mut annotator = PureAnnotator { visitor };
-    module.visit_mut_with(&mut annotator);
-    
+pub fn transform(source: String, options: Option<TransformOptions>) -> Result<String> {
+    let mut options = options.unwrap_or_default();
+    options.apply_filename_defaults();
+    let filename = match options.filename.as_deref() {
+        Some(name) => FileName::Custom(name.to_string()),
+        None => FileName::Anon,
+    };
+
+    // 解析并注解模块
+    let (module, source_map, comments) = annotate(&source, &options, filename)?;
+
     // 生成修改后的代码
     generate_js(&module, source_map, comments)
 }
 
+// 带位置信息的解析/语法诊断，行列均为 1 基
+#[napi(object)]
+pub struct Diagnostic {
+    pub message: String,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+// transform 的结果：代码在无法解析时为 None，诊断一次性返回全部
+#[napi(object)]
+pub struct TransformResult {
+    pub code: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+// 将一个 span 转换为诊断位置，遵循 SWC 的 lookup_char_pos 模型
+fn diagnostic_from_span(source_map: &SourceMap, span: Span, message: String) -> Diagnostic {
+    let lo = source_map.lookup_char_pos(span.lo);
+    let hi = source_map.lookup_char_pos(span.hi);
+    Diagnostic {
+        message,
+        line: lo.line as u32,
+        column: lo.col_display as u32 + 1,
+        end_line: hi.line as u32,
+        end_column: hi.col_display as u32 + 1,
+    }
+}
+
+#[napi]
+pub fn transform_with_diagnostics(source: String, options: Option<TransformOptions>) -> Result<TransformResult> {
+    let mut options = options.unwrap_or_default();
+    options.apply_filename_defaults();
+    let filename = match options.filename.as_deref() {
+        Some(name) => FileName::Custom(name.to_string()),
+        None => FileName::Anon,
+    };
+
+    let source_map = Lrc::new(SourceMap::default());
+    let comments = Lrc::new(SingleThreadedComments::default());
+    let source_file = source_map.new_source_file(filename, source);
+
+    let lexer = Lexer::new(
+        options.to_syntax(),
+        Default::default(),
+        StringInput::from(&*source_file),
+        Some(&comments),
+    );
+
+    let mut parser = Parser::new_from(lexer);
+    let parsed = parser.parse_module();
+
+    // 先收集可恢复的诊断，再处理致命错误，使调用方一次拿到全部位置信息
+    let mut diagnostics: Vec<Diagnostic> = parser
+        .take_errors()
+        .into_iter()
+        .map(|e| diagnostic_from_span(&source_map, e.span(), e.into_kind().msg().to_string()))
+        .collect();
+
+    let mut module = match parsed {
+        Ok(module) => module,
+        Err(e) => {
+            diagnostics.push(diagnostic_from_span(
+                &source_map,
+                e.span(),
+                e.into_kind().msg().to_string(),
+            ));
+            return Ok(TransformResult { code: None, diagnostics });
+        }
+    };
+
+    let config = options.pure.clone().unwrap_or_default().into();
+    let visitor = PureFunctionVisitor::new(source_map.clone(), comments.clone(), config);
+    let mut annotator = PureAnnotator { visitor };
+    module.visit_mut_with(&mut annotator);
+
+    let code = generate_js(&module, source_map, comments)?;
+    Ok(TransformResult { code: Some(code), diagnostics })
+}
+
+// napi 返回值：注解后的代码及其 source map
+#[napi(object)]
+pub struct TransformOutput {
+    pub code: String,
+    // 序列化后的 JSON source map；若内联到 code 中则为 None
+    pub map: Option<String>,
+}
+
+#[napi]
+pub fn transform_with_map(source: String, options: Option<TransformOptions>) -> Result<TransformOutput> {
+    let mut options = options.unwrap_or_default();
+    options.apply_filename_defaults();
+    let filename = match options.filename.as_deref() {
+        Some(name) => FileName::Custom(name.to_string()),
+        None => FileName::Anon,
+    };
+
+    let (module, source_map, comments) = annotate(&source, &options, filename)?;
+    let (mut code, map) = generate_js_with_map(&module, source_map, comments, true)?;
+
+    // 可选地把 source map 内联为 base64 注释，方便直接被 bundler 消费
+    if options.inline_source_map.unwrap_or(false) {
+        if let Some(map) = &map {
+            use base64::Engine as _;
+            let encoded = base64::engine::general_purpose::STANDARD.encode(map.as_bytes());
+            code.push_str("\n//# sourceMappingURL=data:application/json;base64,");
+            code.push_str(&encoded);
+            code.push('\n');
+        }
+        return Ok(TransformOutput { code, map: None });
+    }
+
+    Ok(TransformOutput { code, map })
+}
+
+// 解析源码并运行 PURE 注解访问器，返回修改后的模块及其关联数据。
+fn annotate(
+    source: &str,
+    options: &TransformOptions,
+    filename: FileName,
+) -> Result<(Module, Lrc<SourceMap>, Lrc<SingleThreadedComments>)> {
+    let (mut module, source_map, comments) = parse_js(source, options.to_syntax(), filename)?;
+
+    let config = options.pure.clone().unwrap_or_default().into();
+    let visitor = PureFunctionVisitor::new(source_map.clone(), comments.clone(), config);
+    let mut annotator = PureAnnotator { visitor };
+    module.visit_mut_with(&mut annotator);
+
+    Ok((module, source_map, comments))
+}
+
+// 源码中的一处模块依赖
+#[napi(object)]
+pub struct ModuleDependency {
+    pub specifier: String,
+    pub is_dynamic_import: bool,
+}
+
+// 1 基的行列位置
+#[napi(object)]
+pub struct Position {
+    pub line: u32,
+    pub column: u32,
+}
+
+// analyze_side_effects 的结果
+#[napi(object)]
+pub struct SideEffectResult {
+    // 整个模块是否可判定为无副作用（可用于 "sideEffects": false）
+    pub side_effect_free: bool,
+    pub dependencies: Vec<ModuleDependency>,
+    // 阻止无副作用判定的顶层语句位置
+    pub offending_spans: Vec<Position>,
+}
+
+// 收集动态 import() 调用的依赖
+struct DynamicImportCollector {
+    dependencies: Vec<ModuleDependency>,
+}
+
+impl Visit for DynamicImportCollector {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if matches!(call.callee, Callee::Import(_)) {
+            if let Some(ExprOrSpread { expr, .. }) = call.args.first() {
+                if let Expr::Lit(Lit::Str(s)) = &**expr {
+                    self.dependencies.push(ModuleDependency {
+                        specifier: s.value.to_string(),
+                        is_dynamic_import: true,
+                    });
+                }
+            }
+        }
+        call.visit_children_with(self);
+    }
+}
+
+// 检查某位置是否带有 PURE 前导注释
+fn has_pure_leading(comments: &SingleThreadedComments, lo: BytePos) -> bool {
+    comments.with_leading(lo, |cs| cs.iter().any(|c| c.text.contains("__PURE__")))
+}
+
+// 判断一个在模块求值期间执行的表达式是否产生副作用
+// （用于表达式语句、控制流语句的条件以及变量初始化器）
+fn is_effectful_expr(expr: &Expr, comments: &SingleThreadedComments) -> bool {
+    match expr {
+        // 带 PURE 注解的调用/new 表达式被视为无副作用
+        Expr::Call(c) => !has_pure_leading(comments, c.span.lo),
+        Expr::New(n) => !has_pure_leading(comments, n.span.lo),
+        // 赋值与自增自减直接改变状态
+        Expr::Assign(_) | Expr::Update(_) => true,
+        // 逗号表达式：任一子表达式有副作用即算
+        Expr::Seq(seq) => seq.exprs.iter().any(|e| is_effectful_expr(e, comments)),
+        Expr::Paren(p) => is_effectful_expr(&p.expr, comments),
+        // `delete obj.prop` 改变对象成员
+        Expr::Unary(u) if u.op == UnaryOp::Delete => true,
+        // 其余一元运算符（void/typeof/! 等）的副作用来自其操作数
+        Expr::Unary(u) => is_effectful_expr(&u.arg, comments),
+        // 条件表达式的两个分支均可能产生副作用
+        Expr::Cond(c) => {
+            is_effectful_expr(&c.test, comments)
+                || is_effectful_expr(&c.cons, comments)
+                || is_effectful_expr(&c.alt, comments)
+        }
+        // 其余裸表达式（字面量、标识符等）无副作用
+        _ => false,
+    }
+}
+
+// 记录 span 起点的 1 基行列位置
+fn push_pos(offending: &mut Vec<Position>, source_map: &SourceMap, span: Span) {
+    let loc = source_map.lookup_char_pos(span.lo);
+    offending.push(Position {
+        line: loc.line as u32,
+        column: loc.col_display as u32 + 1,
+    });
+}
+
+// 递归扫描语句，标出会阻止 tree-shaking 的副作用。只深入控制流语句，
+// 不进入函数/类体（其语句在模块求值时并不会执行）。
+fn scan_stmt(
+    stmt: &Stmt,
+    comments: &SingleThreadedComments,
+    source_map: &SourceMap,
+    offending: &mut Vec<Position>,
+) {
+    match stmt {
+        Stmt::Expr(e) => {
+            if is_effectful_expr(&e.expr, comments) {
+                push_pos(offending, source_map, e.span);
+            }
+        }
+        // `throw` 在模块求值期间即产生可观察的副作用
+        Stmt::Throw(t) => push_pos(offending, source_map, t.span),
+        Stmt::Block(b) => b.stmts.iter().for_each(|s| scan_stmt(s, comments, source_map, offending)),
+        Stmt::If(i) => {
+            if is_effectful_expr(&i.test, comments) {
+                push_pos(offending, source_map, i.span);
+            }
+            scan_stmt(&i.cons, comments, source_map, offending);
+            if let Some(alt) = &i.alt {
+                scan_stmt(alt, comments, source_map, offending);
+            }
+        }
+        Stmt::While(w) => {
+            if is_effectful_expr(&w.test, comments) {
+                push_pos(offending, source_map, w.span);
+            }
+            scan_stmt(&w.body, comments, source_map, offending);
+        }
+        Stmt::DoWhile(w) => {
+            if is_effectful_expr(&w.test, comments) {
+                push_pos(offending, source_map, w.span);
+            }
+            scan_stmt(&w.body, comments, source_map, offending);
+        }
+        Stmt::For(f) => {
+            if let Some(test) = &f.test {
+                if is_effectful_expr(test, comments) {
+                    push_pos(offending, source_map, f.span);
+                }
+            }
+            if let Some(update) = &f.update {
+                if is_effectful_expr(update, comments) {
+                    push_pos(offending, source_map, f.span);
+                }
+            }
+            scan_stmt(&f.body, comments, source_map, offending);
+        }
+        Stmt::ForIn(f) => scan_stmt(&f.body, comments, source_map, offending),
+        Stmt::ForOf(f) => scan_stmt(&f.body, comments, source_map, offending),
+        Stmt::Try(t) => {
+            t.block.stmts.iter().for_each(|s| scan_stmt(s, comments, source_map, offending));
+            if let Some(handler) = &t.handler {
+                handler.body.stmts.iter().for_each(|s| scan_stmt(s, comments, source_map, offending));
+            }
+            if let Some(finalizer) = &t.finalizer {
+                finalizer.stmts.iter().for_each(|s| scan_stmt(s, comments, source_map, offending));
+            }
+        }
+        Stmt::Switch(s) => {
+            for case in &s.cases {
+                if let Some(test) = &case.test {
+                    if is_effectful_expr(test, comments) {
+                        push_pos(offending, source_map, s.span);
+                    }
+                }
+                case.cons.iter().for_each(|s| scan_stmt(s, comments, source_map, offending));
+            }
+        }
+        Stmt::Labeled(l) => scan_stmt(&l.body, comments, source_map, offending),
+        // 变量声明的初始化器若是未被注解的调用则有副作用；函数/类声明则仅是声明
+        Stmt::Decl(Decl::Var(var)) => {
+            for d in &var.decls {
+                if let Some(init) = &d.init {
+                    if is_effectful_expr(init, comments) {
+                        push_pos(offending, source_map, d.span);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[napi]
+pub fn analyze_side_effects(source: String, filename: Option<String>) -> Result<SideEffectResult> {
+    let mut options = TransformOptions {
+        filename,
+        ..Default::default()
+    };
+    options.apply_filename_defaults();
+    let file_name = match options.filename.as_deref() {
+        Some(name) => FileName::Custom(name.to_string()),
+        None => FileName::Anon,
+    };
+
+    // 复用注解流程，这样后续可识别出已被标注为 PURE 的调用
+    let (module, source_map, comments) = annotate(&source, &options, file_name)?;
+
+    // 收集静态与动态依赖
+    let mut collector = DynamicImportCollector { dependencies: vec![] };
+    module.visit_with(&mut collector);
+    let mut dependencies = vec![];
+    for item in &module.body {
+        if let ModuleItem::ModuleDecl(decl) = item {
+            match decl {
+                ModuleDecl::Import(i) => dependencies.push(ModuleDependency {
+                    specifier: i.src.value.to_string(),
+                    is_dynamic_import: false,
+                }),
+                ModuleDecl::ExportNamed(n) => {
+                    if let Some(src) = &n.src {
+                        dependencies.push(ModuleDependency {
+                            specifier: src.value.to_string(),
+                            is_dynamic_import: false,
+                        });
+                    }
+                }
+                ModuleDecl::ExportAll(a) => dependencies.push(ModuleDependency {
+                    specifier: a.src.value.to_string(),
+                    is_dynamic_import: false,
+                }),
+                _ => {}
+            }
+        }
+    }
+    dependencies.append(&mut collector.dependencies);
+
+    // 遍历顶层语句（含控制流块内部），标出会阻止 tree-shaking 的副作用
+    let mut offending_spans = vec![];
+    for item in &module.body {
+        if let ModuleItem::Stmt(stmt) = item {
+            scan_stmt(stmt, &comments, &source_map, &mut offending_spans);
+        }
+    }
+
+    Ok(SideEffectResult {
+        side_effect_free: offending_spans.is_empty(),
+        dependencies,
+        offending_spans,
+    })
+}
+
 #[napi]
 pub fn create_plugin(node: JsObject) -> Result<bool> {
     // 解析 Node.js 传入的 AST 节点
@@ -266,21 +863,65 @@ mod tests {
 
     #[test]
     fn test_transform_top_level_calls() -> Result<()> {
+        // 只有返回值被使用的位置才会被注解
         let test_cases = vec![
-            "Object.create({});",
-            "Math.abs(-5);",
-            "Number(42);",
-            "String(123);",
+            "const a = Object.create({});",
+            "const b = Math.abs(-5);",
+            "const c = Number(42);",
+            "const d = String(123);",
         ];
 
         for source in test_cases {
-            let result = transform(source.to_string())?;
+            let result = transform(source.to_string(), None)?;
             assert!(result.contains("/*#__PURE__*/"), "Failed for source: {}", source);
         }
 
         Ok(())
     }
 
+    #[test]
+    fn test_analyze_side_effects_flags_delete_in_block() -> Result<()> {
+        // 嵌套在 if 块内的 delete 成员操作应被标记
+        let source = "const obj = { a: 1 };\nif (globalThis) { delete obj.a; }";
+        let result = analyze_side_effects(source.to_string(), None)?;
+        assert!(!result.side_effect_free);
+        assert!(!result.offending_spans.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_analyze_side_effects_pure_module() -> Result<()> {
+        // 仅有导入与已注解的纯初始化器时，模块可判定为无副作用
+        let source = "import x from 'x';\nexport const y = Object.create({});";
+        let result = analyze_side_effects(source.to_string(), None)?;
+        assert!(result.side_effect_free, "offending: {:?}", result.offending_spans);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transform_export_default_call() -> Result<()> {
+        // `export default` 的表达式值被使用，应被注解
+        let result = transform("export default Object.create({});".to_string(), None)?;
+        assert!(result.contains("/*#__PURE__*/"), "export default call should be annotated");
+        Ok(())
+    }
+
+    #[test]
+    fn test_transform_discards_bare_expression_statements() -> Result<()> {
+        // 裸表达式语句的返回值被丢弃，不应注解
+        let test_cases = vec![
+            "Object.create({});",
+            "sideEffect();",
+        ];
+
+        for source in test_cases {
+            let result = transform(source.to_string(), None)?;
+            assert!(!result.contains("/*#__PURE__*/"), "Failed for source: {}", source);
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_transform_nested_calls() -> Result<()> {
         let test_cases = vec![
@@ -289,7 +930,7 @@ mod tests {
         ];
 
         for source in test_cases {
-            let result = transform(source.to_string())?;
+            let result = transform(source.to_string(), None)?;
             assert!(!result.contains("/*#__PURE__*/"), "Failed for source: {}", source);
         }
 
@@ -304,8 +945,12 @@ mod tests {
         ];
 
         for source in test_cases {
-            let result = transform(source.to_string())?;
+            let result = transform(source.to_string(), None)?;
             assert!(!result.contains("/*#__PURE__*/"), "Failed for source: {}", source);
+            // 裸语句本就不注解，放入初始化器（值上下文）中确认帮助函数仍被排除
+            let declared = format!("const v = {}", source);
+            let result = transform(declared.clone(), None)?;
+            assert!(!result.contains("/*#__PURE__*/"), "Failed for source: {}", declared);
         }
 
         Ok(())